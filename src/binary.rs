@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::{Read, Result};
+use std::path::Path;
+
+const SNIFF_LEN: usize = 8192;
+
+/// Whether the file at `path` looks binary: a NUL byte turns up in the
+/// first few KB, or the sniffed content's MIME type isn't `text/*`.
+pub fn is_binary(path: &Path) -> Result<bool> {
+    let mut file = File::open(path)?;
+    let mut buf = [0u8; SNIFF_LEN];
+    let read = file.read(&mut buf)?;
+    Ok(sniff_is_binary(&buf[..read]))
+}
+
+fn sniff_is_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+
+    !tree_magic_mini::from_u8(sample).starts_with("text/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nul_byte_is_binary() {
+        assert!(sniff_is_binary(&[b'a', b'b', 0, b'c']));
+    }
+
+    #[test]
+    fn plain_text_is_not_binary() {
+        assert!(!sniff_is_binary(b"fn main() {}\n"));
+    }
+
+    #[test]
+    fn png_header_is_binary() {
+        const PNG_MAGIC: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        assert!(sniff_is_binary(PNG_MAGIC));
+    }
+
+    #[test]
+    fn empty_sample_is_not_binary() {
+        assert!(!sniff_is_binary(&[]));
+    }
+}
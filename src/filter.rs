@@ -0,0 +1,127 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Compiled `--exclude` globs plus whether `--respect-gitignore` is set.
+/// The `.gitignore` rule sets picked up while descending the tree are kept
+/// by the caller and passed to `is_excluded`.
+#[derive(Debug)]
+pub struct ExcludeMatcher {
+    globs: GlobSet,
+    respect_gitignore: bool,
+}
+
+impl ExcludeMatcher {
+    pub fn new(patterns: &[String], respect_gitignore: bool) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            add_pattern(&mut builder, pattern).map_err(to_io_error)?;
+        }
+
+        Ok(Self {
+            globs: builder.build().map_err(to_io_error)?,
+            respect_gitignore,
+        })
+    }
+
+    pub fn respects_gitignore(&self) -> bool {
+        self.respect_gitignore
+    }
+
+    /// Whether `path` should be pruned, either by a `--exclude` glob or by
+    /// one of the `.gitignore` rule sets inherited from its ancestors.
+    pub fn is_excluded(&self, path: &Path, gitignore_stack: &[GlobSet]) -> bool {
+        self.globs.is_match(path) || gitignore_stack.iter().any(|set| set.is_match(path))
+    }
+}
+
+/// Compiles the `.gitignore` in `dir`, if any, into a `GlobSet`.
+pub fn load_gitignore(dir: &Path) -> Result<Option<GlobSet>> {
+    let path = dir.join(".gitignore");
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    let mut builder = GlobSetBuilder::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Best-effort: unrecognized glob syntax is just dropped rather
+        // than failing the whole scan over one bad .gitignore line.
+        let _ = add_pattern(&mut builder, line);
+    }
+
+    builder.build().map(Some).map_err(to_io_error)
+}
+
+// Paths fed in by `FileTraverser` carry the traversal prefix (e.g.
+// `./target`, `src/node_modules`), and bare patterns (no `/`) are meant
+// to match at any depth, same as a real `.gitignore`. So every unanchored
+// pattern is compiled twice: once as given, and once prefixed with `**/`
+// so it also matches further down the tree. A leading `/` opts out of
+// that: the rest of the pattern is compiled as-is, so it only matches a
+// path that equals it in full, traversal prefix included (`/src/build`
+// matches `src/build` but not `./src/build`). It is not resolved against
+// the directory of the `.gitignore` it came from.
+fn add_pattern(builder: &mut GlobSetBuilder, pattern: &str) -> std::result::Result<(), globset::Error> {
+    let pattern = pattern.trim_end_matches('/');
+    match pattern.strip_prefix('/') {
+        Some(anchored) => {
+            builder.add(Glob::new(anchored)?);
+        }
+        None => {
+            builder.add(Glob::new(pattern)?);
+            builder.add(Glob::new(&format!("**/{}", pattern))?);
+        }
+    }
+    Ok(())
+}
+
+fn to_io_error(err: globset::Error) -> Error {
+    Error::new(ErrorKind::InvalidInput, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclude_prunes_nested_directories() {
+        let matcher = ExcludeMatcher::new(&["target".to_string()], false).unwrap();
+
+        assert!(matcher.is_excluded(Path::new("./target"), &[]));
+        assert!(matcher.is_excluded(Path::new("./a/target"), &[]));
+        assert!(!matcher.is_excluded(Path::new("./targets"), &[]));
+    }
+
+    #[test]
+    fn gitignore_pattern_matches_below_its_own_directory() {
+        let set = load_gitignore_from_str("node_modules\n*.log\n");
+
+        assert!(set.is_match(Path::new("src/node_modules")));
+        assert!(set.is_match(Path::new("src/deep/nested/node_modules")));
+        assert!(set.is_match(Path::new("build.log")));
+        assert!(!set.is_match(Path::new("node_modules.rs")));
+    }
+
+    #[test]
+    fn anchored_pattern_does_not_match_nested() {
+        let set = load_gitignore_from_str("/build\n");
+
+        assert!(set.is_match(Path::new("build")));
+        assert!(!set.is_match(Path::new("src/build")));
+    }
+
+    fn load_gitignore_from_str(contents: &str) -> GlobSet {
+        let mut builder = GlobSetBuilder::new();
+        for line in contents.lines() {
+            add_pattern(&mut builder, line).unwrap();
+        }
+        builder.build().unwrap()
+    }
+}
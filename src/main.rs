@@ -1,91 +1,235 @@
+mod binary;
+mod filter;
+mod progress;
+mod report;
+mod summary;
+
 use std::fs::{File, ReadDir, DirEntry, read_dir};
 use std::path::{Path, PathBuf};
-use std::io::{Result, BufReader, BufRead, Write, stdout};
+use std::io::{Result, BufReader, BufRead, stdin, stdout};
+use std::sync::mpsc::sync_channel;
+use std::thread;
 use std::time::Instant;
 use clap::{AppSettings, Clap};
+use globset::GlobSet;
+use rayon::iter::{ParallelBridge, ParallelIterator};
+
+use filter::{load_gitignore, ExcludeMatcher};
+use progress::{Event, ProgressSender};
+use report::{reporter_for, OutputFormat};
 
 #[derive(Clap)]
 #[clap(version = env!("CARGO_PKG_VERSION"), author = "Lasse Møldrup <lasse.moeldrup@gmail.com>")]
 #[clap(setting = AppSettings::ColoredHelp)]
 struct Args {
-    #[clap(default_value = "./", about = "Sets the path(s) to search")]
+    #[clap(about = "Sets the path(s) to search (defaults to ./ when neither these nor --paths-from give any path)")]
     paths: Vec<PathBuf>,
     #[clap(short, long, about = "Sets specific file extensions to search")]
     extensions: Option<Vec<String>>,
+    #[clap(long, about = "Excludes paths matching the given glob (repeatable)")]
+    exclude: Vec<String>,
+    #[clap(long, about = "Skips files and directories ignored by .gitignore")]
+    respect_gitignore: bool,
+    #[clap(long, about = "Includes binary files that are skipped by default")]
+    include_binary: bool,
+    #[clap(long, alias = "by-ext", about = "Prints a per-extension summary instead of per-file results")]
+    summary: bool,
+    #[clap(long, default_value = "text", about = "Sets the output format: text, json, or csv")]
+    output: OutputFormat,
+    #[clap(long, about = "Prints live discovery/counting progress to stderr")]
+    progress: bool,
+    #[clap(long, about = "Reads additional newline-separated paths from FILE (- for stdin)")]
+    paths_from: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let extensions = args.extensions.as_ref();
+    let matcher = ExcludeMatcher::new(&args.exclude, args.respect_gitignore)?;
 
     let stdout = stdout();
     let mut handle = stdout.lock();
 
-    let mut total = 0;
     let start = Instant::now();
 
-    let (files, dirs) = args.paths.into_iter()
+    let mut paths = args.paths;
+    if let Some(paths_from) = &args.paths_from {
+        paths.extend(read_paths_from(paths_from)?);
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("./"));
+    }
+
+    let (files, dirs) = paths.into_iter()
         .partition::<Vec<_>, _>(|p| p.is_file());
 
+    let mut results: Vec<(PathBuf, usize)> = Vec::new();
     for file_path in files {
-        write_info(&mut handle, File::open(&file_path)?, &file_path, &mut total)?;
+        if !args.include_binary && binary::is_binary(&file_path)? {
+            continue;
+        }
+        let loc = count_lines(File::open(&file_path)?);
+        results.push((file_path, loc));
     }
 
     if !dirs.is_empty() {
-        for file_info in FileTraverser::traverse(&dirs, extensions)? {
-            let file_info = file_info?;
-            write_info(&mut handle, file_info.file, &file_info.path, &mut total)?;
+        let (progress_tx, progress_handle) = if args.progress {
+            let (tx, handle) = progress::spawn();
+            (tx, Some(handle))
+        } else {
+            (None, None)
+        };
+
+        let mut dir_results = count_dirs_in_parallel(
+            &dirs,
+            extensions,
+            &matcher,
+            !args.include_binary,
+            &progress_tx,
+        )?;
+        dir_results.sort_by(|a, b| a.0.cmp(&b.0));
+        results.append(&mut dir_results);
+
+        drop(progress_tx);
+        if let Some(handle) = progress_handle {
+            handle.join().expect("progress thread panicked");
         }
     }
 
+    let total: usize = results.iter().map(|(_, loc)| loc).sum();
     let elapsed = start.elapsed().as_millis();
-    writeln!(handle, "Total: {}. Completed in {} ms.", total, elapsed)
+
+    let reporter = reporter_for(args.output);
+    if args.summary {
+        let rows = summary::aggregate(&results);
+        reporter.write_summary_report(&mut handle, &rows, total, elapsed)
+    } else {
+        reporter.write_report(&mut handle, &results, total, elapsed)
+    }
+}
+
+// Reads newline-separated paths from `path`, or from stdin when `path`
+// is `-`. Blank lines are skipped.
+fn read_paths_from(path: &Path) -> Result<Vec<PathBuf>> {
+    let reader: Box<dyn BufRead> = if path == Path::new("-") {
+        Box::new(BufReader::new(stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+
+    reader.lines()
+        .filter_map(|line| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(Ok(PathBuf::from(line.trim()))),
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
 }
 
-fn write_info(out: &mut impl Write, file: File, path: &Path, total: &mut usize) -> Result<()> {
-    let loc = BufReader::new(file)
+fn count_lines(file: File) -> usize {
+    BufReader::new(file)
         .lines()
-        .count();
-    *total += loc;
+        .count()
+}
+
+// Discovers files on one thread and counts lines across a work-stealing
+// pool of the rest, so traversal and counting overlap instead of
+// happening one file at a time.
+fn count_dirs_in_parallel(
+    dirs: &[PathBuf],
+    extensions: Option<&Vec<String>>,
+    matcher: &ExcludeMatcher,
+    skip_binary: bool,
+    progress: &ProgressSender,
+) -> Result<Vec<(PathBuf, usize)>> {
+    let (tx, rx) = sync_channel::<PathBuf>(256);
 
-    writeln!(out, "{}\t{}", path.display(), loc)
+    thread::scope(|scope| {
+        let discover = scope.spawn(move || -> Result<()> {
+            for path in FileTraverser::traverse(dirs, extensions, matcher, skip_binary)? {
+                let path = path?;
+                progress::send(progress, Event::Discovered);
+                tx.send(path).map_err(std::io::Error::other)?;
+            }
+            Ok(())
+        });
+
+        let results: Result<Vec<_>> = rx.into_iter()
+            .par_bridge()
+            .map(|path| -> Result<(PathBuf, usize)> {
+                let loc = count_lines(File::open(&path)?);
+                progress::send(progress, Event::Completed);
+                Ok((path, loc))
+            })
+            .collect();
+
+        discover.join().expect("discovery thread panicked")?;
+        results
+    })
 }
 
 
 #[derive(Debug)]
-struct FileTraverser<'a, T> {
-    extensions: Option<&'a Vec<T>>,
-    sub_dirs: Vec<PathBuf>,
+struct FileTraverser<'a> {
+    extensions: Option<&'a Vec<String>>,
+    matcher: &'a ExcludeMatcher,
+    skip_binary: bool,
+    // Directories still to visit, paired with the gitignore rule stack
+    // they inherit from their ancestors.
+    sub_dirs: Vec<(PathBuf, Vec<GlobSet>)>,
+    ignore_stack: Vec<GlobSet>,
     traverser: ReadDir,
 }
 
-impl<'a, T: AsRef<str>> FileTraverser<'a, T> {
+impl<'a> FileTraverser<'a> {
     // Panics if dirs is empty
-    fn traverse(dirs: &[PathBuf], extensions: Option<&'a Vec<T>>) -> Result<Self> {
-        let sub_dirs = dirs[1..].to_vec();
-        let traverser = read_dir(dirs.get(0).expect("Need at least one dir"))?;
+    fn traverse(
+        dirs: &[PathBuf],
+        extensions: Option<&'a Vec<String>>,
+        matcher: &'a ExcludeMatcher,
+        skip_binary: bool,
+    ) -> Result<Self> {
+        let root = dirs.first().expect("Need at least one dir");
+
+        let mut ignore_stack = Vec::new();
+        if matcher.respects_gitignore() {
+            if let Some(set) = load_gitignore(root)? {
+                ignore_stack.push(set);
+            }
+        }
+
+        let sub_dirs = dirs[1..].iter()
+            .map(|dir| (dir.clone(), Vec::new()))
+            .collect();
+        let traverser = read_dir(root)?;
         Ok(Self {
             extensions,
+            matcher,
+            skip_binary,
             sub_dirs,
+            ignore_stack,
             traverser,
         })
     }
 
-    fn map_entry(&mut self, entry: Result<DirEntry>) -> Result<Option<FileInfo>> {
+    fn map_entry(&mut self, entry: Result<DirEntry>) -> Result<Option<PathBuf>> {
         let entry = entry?;
         let path = entry.path();
+        if self.matcher.is_excluded(&path, &self.ignore_stack) {
+            return Ok(None);
+        }
+
         let file_type = entry.file_type()?;
         if file_type.is_file() {
-            match self.extensions {
-                Some(exts) => if has_ext(&entry, exts) {
-                    return File::open(&path)
-                        .map(|file| Some(FileInfo::new(file, &path)));
-                },
-                None => return File::open(&path)
-                    .map(|file| Some(FileInfo::new(file, &path))),
+            let passes_ext = match self.extensions {
+                Some(exts) => has_ext(&entry, exts),
+                None => true,
+            };
+            if passes_ext && !(self.skip_binary && binary::is_binary(&path)?) {
+                return Ok(Some(path));
             }
         } else if file_type.is_dir() {
-            self.sub_dirs.push(path);
+            self.sub_dirs.push((path, self.ignore_stack.clone()));
         }
 
         Ok(None)
@@ -98,15 +242,25 @@ fn has_ext(entry: &DirEntry, exts: &[impl AsRef<str>]) -> bool {
         .ends_with(ext.as_ref()))
 }
 
-impl<'a, T: AsRef<str>> Iterator for FileTraverser<'a, T> {
-    type Item = Result<FileInfo>;
+impl<'a> Iterator for FileTraverser<'a> {
+    type Item = Result<PathBuf>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let entry = match self.traverser.next() {
                 Some(entry) => entry,
                 None => {
-                    self.traverser = match read_dir(self.sub_dirs.pop()?) {
+                    let (dir, ignore_stack) = self.sub_dirs.pop()?;
+                    self.ignore_stack = ignore_stack;
+                    if self.matcher.respects_gitignore() {
+                        match load_gitignore(&dir) {
+                            Ok(Some(set)) => self.ignore_stack.push(set),
+                            Ok(None) => {}
+                            Err(err) => return Some(Err(err)),
+                        }
+                    }
+
+                    self.traverser = match read_dir(&dir) {
                         Ok(traverser) => traverser,
                         Err(err) => return Some(Err(err)),
                     };
@@ -114,27 +268,36 @@ impl<'a, T: AsRef<str>> Iterator for FileTraverser<'a, T> {
                 }
             };
 
-            let file_info = self.map_entry(entry).transpose();
-            if file_info.is_some() {
-                return file_info;
+            let path = self.map_entry(entry).transpose();
+            if path.is_some() {
+                return path;
             }
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
 
-#[derive(Debug)]
-struct FileInfo {
-    file: File,
-    path: PathBuf,
-}
+    // Regression test for a deadlock: the discovery thread's closure used to
+    // only borrow `tx`, so the original `tx` binding outlived the blocking
+    // `collect()` below it, the channel never disconnected, and this call
+    // never returned.
+    #[test]
+    fn count_dirs_in_parallel_scans_a_real_directory() {
+        let dir = std::env::temp_dir().join(format!("locs-count-dirs-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.join("b.txt"), "only one line\n").unwrap();
 
-impl FileInfo {
-    fn new(file: File, path: &Path) -> Self {
-        let path = PathBuf::from(path);
-        Self {
-            file,
-            path,
-        }
+        let matcher = ExcludeMatcher::new(&[], false).unwrap();
+        let results = count_dirs_in_parallel(std::slice::from_ref(&dir), None, &matcher, false, &None).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.iter().map(|(_, loc)| loc).sum::<usize>(), 4);
     }
 }
@@ -0,0 +1,98 @@
+use std::io::{Write, stderr};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+
+const THROTTLE: Duration = Duration::from_millis(100);
+
+pub enum Event {
+    Discovered,
+    Completed,
+}
+
+/// `None` when `--progress` wasn't passed, so callers can send events
+/// unconditionally and pay nothing for it. `Some` once live, shared
+/// across the discovery thread and every counting worker.
+pub type ProgressSender = Option<Sender<Event>>;
+
+pub fn send(tx: &ProgressSender, event: Event) {
+    if let Some(tx) = tx {
+        let _ = tx.send(event);
+    }
+}
+
+/// Spawns a reporter thread that aggregates discovery/completion counts
+/// and prints a throttled line to stderr, leaving stdout clean for
+/// results. Returns the sender side to wire into the scan and a handle
+/// to join once it's done.
+pub fn spawn() -> (ProgressSender, JoinHandle<()>) {
+    let (tx, rx) = unbounded();
+    let handle = thread::spawn(move || run(rx));
+    (Some(tx), handle)
+}
+
+// Plain event tallies, kept separate from the I/O loop below so the
+// aggregation logic can be unit-tested without a real channel or timer.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Counts {
+    discovered: usize,
+    completed: usize,
+}
+
+impl Counts {
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Discovered => self.discovered += 1,
+            Event::Completed => self.completed += 1,
+        }
+    }
+
+    fn line(&self) -> String {
+        format!("\r{} discovered, {} counted", self.discovered, self.completed)
+    }
+}
+
+fn run(rx: Receiver<Event>) {
+    let mut counts = Counts::default();
+    let mut last_print = Instant::now();
+    let mut err = stderr();
+
+    loop {
+        match rx.recv_timeout(THROTTLE) {
+            Ok(event) => counts.apply(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_print.elapsed() >= THROTTLE {
+            let _ = write!(err, "{}", counts.line());
+            let _ = err.flush();
+            last_print = Instant::now();
+        }
+    }
+
+    let _ = writeln!(err, "{}", counts.line());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_discovered_and_completed_separately() {
+        let mut counts = Counts::default();
+        counts.apply(Event::Discovered);
+        counts.apply(Event::Discovered);
+        counts.apply(Event::Completed);
+
+        assert_eq!(counts, Counts { discovered: 2, completed: 1 });
+        assert_eq!(counts.line(), "\r2 discovered, 1 counted");
+    }
+
+    #[test]
+    fn send_without_a_sender_is_a_no_op() {
+        let tx: ProgressSender = None;
+        send(&tx, Event::Discovered);
+    }
+}
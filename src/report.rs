@@ -0,0 +1,225 @@
+use std::io::{Error, Result, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::summary::SummaryRow;
+
+/// Selects how results are written: the original tab-separated text, or
+/// machine-readable JSON/CSV for piping into other tooling.
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("unknown output format `{}` (expected text, json, or csv)", other)),
+        }
+    }
+}
+
+/// Writes the per-file results and the trailing total as a single report,
+/// so adding an output mode means adding one impl here instead of
+/// scattering format checks through `main`. A single method per format
+/// (rather than separate results/total calls) keeps JSON a single
+/// top-level value instead of two concatenated documents.
+pub trait Reporter {
+    fn write_report(
+        &self,
+        out: &mut dyn Write,
+        results: &[(PathBuf, usize)],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()>;
+
+    fn write_summary_report(
+        &self,
+        out: &mut dyn Write,
+        rows: &[SummaryRow],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()>;
+}
+
+pub fn reporter_for(format: OutputFormat) -> Box<dyn Reporter> {
+    match format {
+        OutputFormat::Text => Box::new(TextReporter),
+        OutputFormat::Json => Box::new(JsonReporter),
+        OutputFormat::Csv => Box::new(CsvReporter),
+    }
+}
+
+struct TextReporter;
+
+impl Reporter for TextReporter {
+    fn write_report(
+        &self,
+        out: &mut dyn Write,
+        results: &[(PathBuf, usize)],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        for (path, loc) in results {
+            writeln!(out, "{}\t{}", path.display(), loc)?;
+        }
+        writeln!(out, "Total: {}. Completed in {} ms.", total, elapsed_ms)
+    }
+
+    fn write_summary_report(
+        &self,
+        out: &mut dyn Write,
+        rows: &[SummaryRow],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        for (ext, files, lines) in rows {
+            writeln!(out, "{}\t{}\t{}", ext, files, lines)?;
+        }
+        writeln!(out, "Total: {}. Completed in {} ms.", total, elapsed_ms)
+    }
+}
+
+struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn write_report(
+        &self,
+        out: &mut dyn Write,
+        results: &[(PathBuf, usize)],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        let entries: Vec<_> = results.iter()
+            .map(|(path, loc)| serde_json::json!({ "path": path, "lines": loc }))
+            .collect();
+        let report = serde_json::json!({
+            "entries": entries,
+            "total": total,
+            "elapsed_ms": elapsed_ms,
+        });
+        serde_json::to_writer(&mut *out, &report).map_err(to_io_error)?;
+        writeln!(out)
+    }
+
+    fn write_summary_report(
+        &self,
+        out: &mut dyn Write,
+        rows: &[SummaryRow],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        let by_extension: Vec<_> = rows.iter()
+            .map(|(ext, files, lines)| serde_json::json!({
+                "extension": ext,
+                "files": files,
+                "lines": lines,
+            }))
+            .collect();
+        let report = serde_json::json!({
+            "by_extension": by_extension,
+            "total": total,
+            "elapsed_ms": elapsed_ms,
+        });
+        serde_json::to_writer(&mut *out, &report).map_err(to_io_error)?;
+        writeln!(out)
+    }
+}
+
+struct CsvReporter;
+
+impl Reporter for CsvReporter {
+    fn write_report(
+        &self,
+        out: &mut dyn Write,
+        results: &[(PathBuf, usize)],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        writeln!(out, "path,lines")?;
+        for (path, loc) in results {
+            writeln!(out, "{},{}", csv_field(&path.display().to_string()), loc)?;
+        }
+        writeln!(out, "total,elapsed_ms")?;
+        writeln!(out, "{},{}", total, elapsed_ms)
+    }
+
+    fn write_summary_report(
+        &self,
+        out: &mut dyn Write,
+        rows: &[SummaryRow],
+        total: usize,
+        elapsed_ms: u128,
+    ) -> Result<()> {
+        writeln!(out, "extension,files,lines")?;
+        for (ext, files, lines) in rows {
+            writeln!(out, "{},{},{}", csv_field(ext), files, lines)?;
+        }
+        writeln!(out, "total,elapsed_ms")?;
+        writeln!(out, "{},{}", total, elapsed_ms)
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote, or newline, doubling
+/// any embedded quotes, per RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains(['"', ',', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_io_error(err: serde_json::Error) -> Error {
+    Error::other(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_fields_with_commas() {
+        assert_eq!(csv_field("weird,name.rs"), "\"weird,name.rs\"");
+    }
+
+    #[test]
+    fn csv_escapes_embedded_quotes() {
+        assert_eq!(csv_field("a\"b.rs"), "\"a\"\"b.rs\"");
+    }
+
+    #[test]
+    fn csv_leaves_plain_fields_untouched() {
+        assert_eq!(csv_field("src/main.rs"), "src/main.rs");
+    }
+
+    #[test]
+    fn csv_report_keeps_rows_aligned_with_commas_in_path() {
+        let mut out = Vec::new();
+        let results = vec![(PathBuf::from("weird,name.rs"), 3usize)];
+        CsvReporter.write_report(&mut out, &results, 3, 1).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let row = text.lines().nth(1).unwrap();
+        assert_eq!(row, "\"weird,name.rs\",3");
+    }
+
+    #[test]
+    fn json_report_is_a_single_value() {
+        let mut out = Vec::new();
+        let results = vec![(PathBuf::from("src/main.rs"), 10usize)];
+        JsonReporter.write_report(&mut out, &results, 10, 5).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["total"], 10);
+        assert_eq!(value["elapsed_ms"], 5);
+        assert_eq!(value["entries"][0]["lines"], 10);
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Extension, file count, and line count for one row of a `--summary`
+/// report, naturally sorted by extension.
+pub type SummaryRow = (String, usize, usize);
+
+/// Groups `(path, lines)` results by file extension.
+pub fn aggregate(results: &[(PathBuf, usize)]) -> Vec<SummaryRow> {
+    let mut by_ext: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (path, loc) in results {
+        let entry = by_ext.entry(extension_of(path)).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += loc;
+    }
+
+    let mut rows: Vec<_> = by_ext.into_iter()
+        .map(|(ext, (files, lines))| (ext, files, lines))
+        .collect();
+    rows.sort_by(|(a, ..), (b, ..)| alphanumeric_sort::compare_str(a, b));
+    rows
+}
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "(none)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_and_sorts_naturally() {
+        let results = vec![
+            (PathBuf::from("a.rs"), 10),
+            (PathBuf::from("b.rs"), 5),
+            (PathBuf::from("c.md"), 3),
+            (PathBuf::from("no_ext"), 1),
+        ];
+
+        let rows = aggregate(&results);
+        assert_eq!(rows, vec![
+            ("(none)".to_string(), 1, 1),
+            ("md".to_string(), 1, 3),
+            ("rs".to_string(), 2, 15),
+        ]);
+    }
+}